@@ -43,15 +43,14 @@ impl Contract {
     /// Create a new valid mapping from given code bytes.
     /// it gives back ValidJumpAddress and size od needed paddings.
     fn analize(code: &[u8]) -> (ValidJumpAddress, usize) {
-        let mut jumps: Vec<bool> = Vec::with_capacity(code.len());
-        jumps.resize(code.len(), false);
+        let mut jumps = ValidJumpAddress::with_capacity(code.len());
         let mut is_push_last = false;
         let mut i = 0;
         while i < code.len() {
             let opcode = code[i] as u8;
             if opcode == opcode::JUMPDEST as u8 {
                 is_push_last = false;
-                jumps[i] = true;
+                jumps.set(i);
                 i += 1;
             } else if let Some(v) = OpCode::is_push(opcode) {
                 is_push_last = true;
@@ -63,7 +62,7 @@ impl Contract {
         }
         let padding = if is_push_last { i - code.len() } else { 0 };
 
-        (ValidJumpAddress(jumps), padding)
+        (jumps, padding)
     }
 
     pub fn is_valid_jump(&self, possition: usize) -> bool {
@@ -97,16 +96,32 @@ impl Contract {
     }
 }
 
-/// Mapping of valid jump destination from code.
+const BITS_PER_WORD: usize = u64::BITS as usize;
+
+/// Mapping of valid jump destinations from code, packed one bit per byte.
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct ValidJumpAddress(Vec<bool>);
+pub struct ValidJumpAddress {
+    len: usize,
+    words: Vec<u64>,
+}
 
 impl ValidJumpAddress {
+    fn with_capacity(len: usize) -> Self {
+        let word_count = (len + BITS_PER_WORD - 1) / BITS_PER_WORD;
+        let mut words = Vec::with_capacity(word_count);
+        words.resize(word_count, 0u64);
+        Self { len, words }
+    }
+
+    fn set(&mut self, position: usize) {
+        self.words[position / BITS_PER_WORD] |= 1u64 << (position % BITS_PER_WORD);
+    }
+
     /// Get the length of the valid mapping. This is the same as the
     /// code bytes.
     #[inline]
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.len
     }
 
     /// Returns true if the valids list is empty
@@ -118,11 +133,11 @@ impl ValidJumpAddress {
     /// Returns `true` if the position is a valid jump destination. If
     /// not, returns `false`.
     pub fn is_valid(&self, position: usize) -> bool {
-        if position >= self.0.len() {
+        if position >= self.len {
             return false;
         }
 
-        self.0[position]
+        self.words[position / BITS_PER_WORD] & (1u64 << (position % BITS_PER_WORD)) != 0
     }
 }
 
@@ -140,4 +155,12 @@ mod test {
         let (_, padding) = Contract::analize(&[opcode::CODESIZE, opcode::PUSH3, 0x00]);
         assert_eq!(padding, 2, "Padding should be zero");
     }
+
+    #[test]
+    fn jumpdest_bitmap_skips_push_immediates() {
+        let (jumps, _) = Contract::analize(&[opcode::PUSH1, opcode::JUMPDEST, opcode::JUMPDEST]);
+        assert!(!jumps.is_valid(1), "PUSH1 immediate is not a jumpdest");
+        assert!(jumps.is_valid(2), "standalone JUMPDEST is valid");
+        assert!(!jumps.is_valid(3), "out of bounds position is invalid");
+    }
 }
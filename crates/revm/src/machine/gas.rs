@@ -0,0 +1,296 @@
+use crate::{instructions::Return, return_ok, return_revert, spec::Spec};
+use primitive_types::{H160, U256};
+
+/// Integer representation backing `Gas<T>`'s bookkeeping (narrow `u64`/`usize`, or wide `U256`).
+pub trait CostType: Sized + Copy + PartialOrd + core::fmt::Debug {
+    /// Build a value of this type from a `u64`. Lossless for `u64` and
+    /// `U256`; the `usize` implementation truncates on targets where
+    /// `usize` is narrower than 64 bits.
+    fn from_u64(v: u64) -> Self;
+
+    /// Try to build a value of this type from a `U256`, failing if `v`
+    /// doesn't fit.
+    fn try_from_u256(v: U256) -> Option<Self>;
+
+    /// Narrow this value down to a `u64`, saturating instead of panicking if
+    /// it doesn't fit. For display/tracing only: never use this to compare
+    /// or do arithmetic, since it's lossy for the wide `U256` path.
+    fn saturating_as_u64(self) -> u64;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn checked_div(self, other: Self) -> Option<Self>;
+}
+
+impl CostType for u64 {
+    #[inline]
+    fn from_u64(v: u64) -> Self {
+        v
+    }
+    #[inline]
+    fn try_from_u256(v: U256) -> Option<Self> {
+        if v > U256::from(u64::MAX) {
+            None
+        } else {
+            Some(v.as_u64())
+        }
+    }
+    #[inline]
+    fn saturating_as_u64(self) -> u64 {
+        self
+    }
+    #[inline]
+    fn checked_add(self, other: Self) -> Option<Self> {
+        u64::checked_add(self, other)
+    }
+    #[inline]
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        u64::checked_sub(self, other)
+    }
+    #[inline]
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        u64::checked_mul(self, other)
+    }
+    #[inline]
+    fn checked_div(self, other: Self) -> Option<Self> {
+        u64::checked_div(self, other)
+    }
+}
+
+impl CostType for usize {
+    #[inline]
+    fn from_u64(v: u64) -> Self {
+        v as usize
+    }
+    #[inline]
+    fn try_from_u256(v: U256) -> Option<Self> {
+        if v > U256::from(usize::MAX as u64) {
+            None
+        } else {
+            Some(v.as_usize())
+        }
+    }
+    #[inline]
+    fn saturating_as_u64(self) -> u64 {
+        self as u64
+    }
+    #[inline]
+    fn checked_add(self, other: Self) -> Option<Self> {
+        usize::checked_add(self, other)
+    }
+    #[inline]
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        usize::checked_sub(self, other)
+    }
+    #[inline]
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        usize::checked_mul(self, other)
+    }
+    #[inline]
+    fn checked_div(self, other: Self) -> Option<Self> {
+        usize::checked_div(self, other)
+    }
+}
+
+impl CostType for U256 {
+    #[inline]
+    fn from_u64(v: u64) -> Self {
+        U256::from(v)
+    }
+    #[inline]
+    fn try_from_u256(v: U256) -> Option<Self> {
+        Some(v)
+    }
+    #[inline]
+    fn saturating_as_u64(self) -> u64 {
+        if self > U256::from(u64::MAX) {
+            u64::MAX
+        } else {
+            self.as_u64()
+        }
+    }
+    #[inline]
+    fn checked_add(self, other: Self) -> Option<Self> {
+        self.checked_add(other)
+    }
+    #[inline]
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_sub(other)
+    }
+    #[inline]
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        self.checked_mul(other)
+    }
+    #[inline]
+    fn checked_div(self, other: Self) -> Option<Self> {
+        self.checked_div(other)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Gas<T: CostType = u64> {
+    limit: T,
+    used: T,
+    memory: T,
+    refunded: i64,
+    all_used_gas: T,
+}
+
+impl<T: CostType> Gas<T> {
+    pub fn new(limit: T) -> Self {
+        Self {
+            limit,
+            used: T::from_u64(0),
+            memory: T::from_u64(0),
+            refunded: 0,
+            all_used_gas: T::from_u64(0),
+        }
+    }
+
+    pub fn reimburse_unspend(&mut self, exit: &Return, other: Gas<T>) {
+        match *exit {
+            return_ok!() => {
+                self.erase_cost(other.remaining());
+                self.record_refund(other.refunded());
+            }
+            return_revert!() => {
+                self.erase_cost(other.remaining());
+            }
+            _ => {}
+        }
+    }
+
+    pub fn limit(&self) -> T {
+        self.limit
+    }
+
+    pub fn memory(&self) -> T {
+        self.memory
+    }
+
+    pub fn refunded(&self) -> i64 {
+        self.refunded
+    }
+
+    pub fn spend(&self) -> T {
+        self.all_used_gas
+    }
+
+    pub fn remaining(&self) -> T {
+        self.limit
+            .checked_sub(self.all_used_gas)
+            .expect("all_used_gas never exceeds limit")
+    }
+
+    pub fn erase_cost(&mut self, returned: T) {
+        self.used = self.used.checked_sub(returned).expect("underflow");
+        self.all_used_gas = self
+            .all_used_gas
+            .checked_sub(returned)
+            .expect("underflow");
+    }
+
+    pub fn record_refund(&mut self, refund: i64) {
+        self.refunded += refund;
+    }
+
+    /// Record an explict cost.
+    #[inline(always)]
+    pub fn record_cost(&mut self, cost: T) -> bool {
+        let all_used_gas = match self.all_used_gas.checked_add(cost) {
+            Some(all_used_gas) => all_used_gas,
+            None => return false,
+        };
+        if self.limit < all_used_gas {
+            return false;
+        }
+
+        self.used = self.used.checked_add(cost).expect("checked above");
+        self.all_used_gas = all_used_gas;
+        true
+    }
+
+    /// used in memory_resize! macro
+    #[inline(always)]
+    pub fn record_memory(&mut self, gas_memory: T) -> bool {
+        if gas_memory > self.memory {
+            let all_used_gas = match self.used.checked_add(gas_memory) {
+                Some(all_used_gas) => all_used_gas,
+                None => return false,
+            };
+            if self.limit < all_used_gas {
+                return false;
+            }
+            self.memory = gas_memory;
+            self.all_used_gas = all_used_gas;
+        }
+        true
+    }
+
+    /// used in gas_refund! macro
+    pub fn gas_refund(&mut self, refund: i64) {
+        self.refunded += refund;
+    }
+
+    /// Price and charge an `ExternalOperation` against `SPEC`'s cost
+    /// schedule. Returns `false` on out-of-gas, like `record_cost`. No `Host`
+    /// implementation or opcode handler calls this method: `Host`
+    /// account/code/storage accesses are not charged through it.
+    #[inline]
+    pub fn record_external_operation<SPEC: Spec>(&mut self, op: ExternalOperation) -> bool {
+        let cost = match op {
+            ExternalOperation::AccountBasicRead => SPEC::GAS_BALANCE,
+            ExternalOperation::AddressCodeRead(_) => SPEC::GAS_EXT_CODE,
+            ExternalOperation::IsEmpty => SPEC::GAS_EXT_CODE,
+            ExternalOperation::Write => SPEC::GAS_SSTORE_SET,
+        };
+        self.record_cost(T::from_u64(cost))
+    }
+}
+
+/// A state-dependent operation priceable via `Gas::record_external_operation`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExternalOperation {
+    /// Reading an account's basic info (balance, nonce, ...).
+    AccountBasicRead,
+    /// Reading the code stored at `address`.
+    AddressCodeRead(H160),
+    /// Checking whether an account is empty.
+    IsEmpty,
+    /// Writing to storage (or otherwise touching/creating an account).
+    Write,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn narrow_and_wide_agree_on_record_cost() {
+        let mut narrow = Gas::<u64>::new(100);
+        let mut wide = Gas::<U256>::new(U256::from(100));
+
+        assert_eq!(narrow.record_cost(40), wide.record_cost(U256::from(40)));
+        assert_eq!(narrow.remaining(), wide.remaining().saturating_as_u64());
+
+        assert_eq!(narrow.record_cost(70), wide.record_cost(U256::from(70)));
+    }
+
+    #[test]
+    fn try_from_u256_rejects_oversized_narrow_limit() {
+        let huge = U256::MAX;
+        assert!(u64::try_from_u256(huge).is_none());
+        assert!(U256::try_from_u256(huge).is_some());
+    }
+
+    #[test]
+    fn wide_gas_above_u64_max_does_not_panic_on_record_cost() {
+        let huge_limit = U256::from(u64::MAX) + U256::from(1_000_000);
+        let mut wide = Gas::<U256>::new(huge_limit);
+
+        assert!(wide.record_cost(huge_limit));
+        assert_eq!(wide.remaining(), U256::zero());
+        assert_eq!(wide.spend().saturating_as_u64(), u64::MAX);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::alloc::vec::Vec;
+use primitive_types::{H160, H256, U256};
+
+// This journal is not wired into `Host`'s call/create return handling:
+// nothing in the crate takes a `Snapshot` before a nested frame, `commit`s or
+// `revert_to`s it based on how the frame exits, or applies the returned
+// entries to undo state. The module is standalone and unreferenced.
+
+/// A handle to a point in the undo journal, taken before a nested call/create
+/// frame. Not yet exposed on `Host` or taken at any real call/create boundary.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Snapshot {
+    /// Number of journal entries that existed when the checkpoint was taken.
+    /// Reverting truncates the journal back down to this length.
+    journal_len: usize,
+    /// Call depth the checkpoint was taken at, for sanity-checking nested
+    /// commit/revert pairs line up with `Machine::call_depth`.
+    call_depth: u64,
+}
+
+impl Snapshot {
+    pub fn call_depth(&self) -> u64 {
+        self.call_depth
+    }
+}
+
+/// One undo entry: enough to put the world back how it was before the mutation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum JournalEntry {
+    /// `address`'s storage slot `key` held `had_value` before being written.
+    StorageChange {
+        address: H160,
+        key: H256,
+        had_value: U256,
+    },
+    /// `address`'s transient storage slot `key` held `had_value` before
+    /// being written.
+    TransientStorageChange {
+        address: H160,
+        key: H256,
+        had_value: U256,
+    },
+    /// `address`'s balance was `had_balance` before being debited/credited.
+    BalanceChange { address: H160, had_balance: U256 },
+    /// `address` did not exist before this frame and was created by it.
+    AccountCreated { address: H160 },
+    /// The log list had `had_len` entries before this frame appended to it.
+    LogsAppended { had_len: usize },
+}
+
+/// Undo log of mutating `Host` operations, truncated by `revert_to` and grouped into frames
+/// by `Snapshot`s. Not yet wired into `Host` or driven from a real call path.
+#[derive(Clone, Debug, Default)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Take a checkpoint at the current call depth. Pair with `commit` or
+    /// `revert_to`.
+    pub fn checkpoint(&self, call_depth: u64) -> Snapshot {
+        Snapshot {
+            journal_len: self.entries.len(),
+            call_depth,
+        }
+    }
+
+    /// Record that `entry`'s mutation is about to happen, so it can be
+    /// undone later.
+    pub fn push(&mut self, entry: JournalEntry) {
+        self.entries.push(entry);
+    }
+
+    /// The frame succeeded: its entries stay, committed into the parent frame.
+    pub fn commit(&mut self, _snapshot: Snapshot) {}
+
+    /// The frame reverted: drain every entry recorded since `snapshot` and
+    /// return them so the caller can undo each one against its own state.
+    /// `Journal` only records entries; it has no access to the state they
+    /// describe, so it cannot apply the undo itself.
+    pub fn revert_to(&mut self, snapshot: Snapshot) -> Vec<JournalEntry> {
+        self.entries.split_off(snapshot.journal_len)
+    }
+
+    /// Number of entries currently in the journal; entries drained by
+    /// `revert_to` are not counted. Exposed mainly for tests and diagnostics.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn revert_only_undoes_entries_after_the_checkpoint() {
+        let mut journal = Journal::new();
+        journal.push(JournalEntry::AccountCreated {
+            address: H160::zero(),
+        });
+        let snapshot = journal.checkpoint(1);
+        journal.push(JournalEntry::BalanceChange {
+            address: H160::zero(),
+            had_balance: U256::zero(),
+        });
+
+        let undone = journal.revert_to(snapshot);
+        assert_eq!(undone.len(), 1);
+        assert_eq!(journal.len(), 1);
+    }
+
+    #[test]
+    fn commit_keeps_all_entries() {
+        let mut journal = Journal::new();
+        let snapshot = journal.checkpoint(0);
+        journal.push(JournalEntry::LogsAppended { had_len: 0 });
+        journal.commit(snapshot);
+        assert_eq!(journal.len(), 1);
+    }
+}
@@ -1,17 +1,26 @@
-use crate::{
-    instructions::{eval, Return},
-    return_ok, return_revert,
-};
+use crate::instructions::{eval, Return};
 use bytes::Bytes;
 use core::ops::Range;
+use primitive_types::U256;
 
 use super::{contract::Contract, memory::Memory, stack::Stack};
 use crate::{spec::Spec, Host};
 
+mod gas;
+mod journal;
+mod precompiles;
+#[cfg(feature = "tracing")]
+mod trace;
+pub use gas::{CostType, ExternalOperation, Gas};
+pub use journal::{Journal, JournalEntry, Snapshot};
+pub use precompiles::call_precompile;
+#[cfg(feature = "tracing")]
+pub use trace::{OpcodeTrace, Tracer};
+
 pub const STACK_LIMIT: u64 = 1024;
 pub const CALL_STACK_LIMIT: u64 = 1024;
 
-pub struct Machine {
+pub struct Machine<T: CostType = u64> {
     /// Contract information and invoking data
     pub contract: Contract,
     /// Program counter.
@@ -25,107 +34,41 @@ pub struct Machine {
     /// After call returns, its return data is saved here.
     pub return_data_buffer: Bytes,
     /// left gas. Memory gas can be found in Memory field.
-    pub gas: Gas,
+    pub gas: Gas<T>,
     /// used only for inspector.
     pub call_depth: u64,
+    /// Sink for the per-opcode structured trace records described in
+    /// `trace::Tracer`. Only present when the `tracing` feature is enabled,
+    /// so disabled builds pay no size or branch cost for it.
+    #[cfg(feature = "tracing")]
+    pub tracer: Option<crate::alloc::boxed::Box<dyn Tracer>>,
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct Gas {
-    limit: u64,
-    used: u64,
-    memory: u64,
-    refunded: i64,
-    all_used_gas: u64,
+/// A `Machine` picked for the gas-limit representation a caller supplied.
+/// Only `Narrow` can actually execute a contract today: `run`/`step` exist
+/// solely on `Machine<u64>` (see below), because `Host`/`eval` aren't generic
+/// over `CostType`. `Wide` is usable for gas bookkeeping and introspection
+/// (`contract()`, `gas()`, `stack()`, ...) only — it cannot run bytecode
+/// until `Host`/`eval` are generalized, which hasn't happened.
+pub enum AnyMachine {
+    Narrow(Machine<u64>),
+    Wide(Machine<U256>),
 }
-impl Gas {
-    pub fn new(limit: u64) -> Self {
-        Self {
-            limit,
-            used: 0,
-            memory: 0,
-            refunded: 0,
-            all_used_gas: 0,
-        }
-    }
-
-    pub fn reimburse_unspend(&mut self, exit: &Return, other: Gas) {
-        match *exit {
-            return_ok!() => {
-                self.erase_cost(other.remaining());
-                self.record_refund(other.refunded());
-            }
-            return_revert!() => {
-                self.erase_cost(other.remaining());
-            }
-            _ => {}
-        }
-    }
-
-    pub fn limit(&self) -> u64 {
-        self.limit
-    }
-
-    pub fn memory(&self) -> u64 {
-        self.memory
-    }
-
-    pub fn refunded(&self) -> i64 {
-        self.refunded
-    }
-
-    pub fn spend(&self) -> u64 {
-        self.all_used_gas
-    }
 
-    pub fn remaining(&self) -> u64 {
-        self.limit - self.all_used_gas
-    }
-
-    pub fn erase_cost(&mut self, returned: u64) {
-        self.used -= returned;
-        self.all_used_gas -= returned;
-    }
-
-    pub fn record_refund(&mut self, refund: i64) {
-        self.refunded += refund;
-    }
-
-    /// Record an explict cost.
-    #[inline(always)]
-    pub fn record_cost(&mut self, cost: u64) -> bool {
-        let (all_used_gas, overflow) = self.all_used_gas.overflowing_add(cost);
-        if overflow || self.limit < all_used_gas {
-            return false;
+impl AnyMachine {
+    /// Construct a `Machine`, picking the narrow `u64` representation
+    /// whenever `gas_limit` fits and falling back to `U256` otherwise. A
+    /// `Wide` result cannot run `contract` (see `AnyMachine`'s docs above).
+    pub fn new<SPEC: Spec>(contract: Contract, gas_limit: U256, call_depth: u64) -> Self {
+        match u64::try_from_u256(gas_limit) {
+            Some(limit) => AnyMachine::Narrow(Machine::new::<SPEC>(contract, limit, call_depth)),
+            None => AnyMachine::Wide(Machine::new::<SPEC>(contract, gas_limit, call_depth)),
         }
-
-        self.used += cost;
-        self.all_used_gas = all_used_gas;
-        true
-    }
-
-    /// used in memory_resize! macro
-    #[inline(always)]
-    pub fn record_memory(&mut self, gas_memory: u64) -> bool {
-        if gas_memory > self.memory {
-            let (all_used_gas, overflow) = self.used.overflowing_add(gas_memory);
-            if overflow || self.limit < all_used_gas {
-                return false;
-            }
-            self.memory = gas_memory;
-            self.all_used_gas = all_used_gas;
-        }
-        true
-    }
-
-    /// used in gas_refund! macro
-    pub fn gas_refund(&mut self, refund: i64) {
-        self.refunded += refund;
     }
 }
 
-impl Machine {
-    pub fn new<SPEC: Spec>(contract: Contract, gas_limit: u64, call_depth: u64) -> Self {
+impl<T: CostType> Machine<T> {
+    pub fn new<SPEC: Spec>(contract: Contract, gas_limit: T, call_depth: u64) -> Self {
         Self {
             program_counter: contract.code.as_ptr(),
             return_range: Range::default(),
@@ -135,13 +78,15 @@ impl Machine {
             contract,
             gas: Gas::new(gas_limit),
             call_depth,
+            #[cfg(feature = "tracing")]
+            tracer: None,
         }
     }
     pub fn contract(&self) -> &Contract {
         &self.contract
     }
 
-    pub fn gas(&mut self) -> &Gas {
+    pub fn gas(&mut self) -> &Gas<T> {
         &self.gas
     }
 
@@ -155,6 +100,48 @@ impl Machine {
         unsafe { self.program_counter.offset_from(self.contract.code.as_ptr()) as usize}
     }
 
+    /// Copy and get the return value of the machine, if any.
+    pub fn return_value(&self) -> Bytes {
+        // if start is usize max it means that our return len is zero and we need to return empty
+        if self.return_range.start == usize::MAX {
+            Bytes::new()
+        } else {
+            Bytes::copy_from_slice(self.memory.get_slice(
+                self.return_range.start,
+                self.return_range.end - self.return_range.start,
+            ))
+        }
+    }
+}
+
+// `Host::step`/`step_end` and `instructions::eval` predate `Machine<T>` and
+// still refer to the type as plain `Machine`, which resolves to `Machine<u64>`
+// via the struct's default type parameter. So only `Machine<u64>` can drive
+// `run`/`step` until those signatures are made generic over `CostType` too;
+// `Machine<U256>` is usable for gas bookkeeping but can't run the interpreter
+// loop yet.
+impl Machine<u64> {
+    /// Runs `contract` as a precompile if its address is one, otherwise
+    /// interprets it as EVM bytecode. Nothing in the crate calls it: the
+    /// CALL/CREATE opcode handling in `instructions::eval` does not go
+    /// through this method, so precompile addresses are not dispatched there.
+    pub fn call<H: Host, SPEC: Spec>(
+        contract: Contract,
+        gas_limit: u64,
+        call_depth: u64,
+        host: &mut H,
+    ) -> (Return, Bytes, Gas<u64>) {
+        let mut gas = Gas::new(gas_limit);
+        if let Some((ret, out)) = call_precompile(contract.address, &contract.input, &mut gas) {
+            return (ret, out, gas);
+        }
+
+        let mut machine = Machine::new::<SPEC>(contract, gas_limit, call_depth);
+        let ret = machine.run::<H, SPEC>(host);
+        let out = machine.return_value();
+        (ret, out, machine.gas)
+    }
+
     /// loop steps until we are finished with execution
     pub fn run<H: Host, SPEC: Spec>(&mut self, host: &mut H) -> Return {
         let mut ret = Return::Continue;
@@ -173,10 +160,27 @@ impl Machine {
                 return ret;
             }
         }
+        #[cfg(feature = "tracing")]
+        let trace_program_counter = self.program_counter();
+        #[cfg(feature = "tracing")]
+        let gas_spent_before = self.gas.spend();
+
         let opcode = unsafe {*self.program_counter};
         self.program_counter = unsafe { self.program_counter.offset(1)};
         let eval = eval::<H, SPEC>(self, opcode, host);
-        
+
+        #[cfg(feature = "tracing")]
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.trace_opcode(OpcodeTrace {
+                program_counter: trace_program_counter,
+                opcode,
+                gas_remaining: self.gas.remaining(),
+                gas_cost: self.gas.spend() - gas_spent_before,
+                stack_depth: self.stack.len(),
+                memory_size: self.memory.len(),
+            });
+        }
+
         if H::INSPECT {
             let ret = host.step_end(eval, self);
             if ret != Return::Continue {
@@ -186,17 +190,4 @@ impl Machine {
 
         eval
     }
-
-    /// Copy and get the return value of the machine, if any.
-    pub fn return_value(&self) -> Bytes {
-        // if start is usize max it means that our return len is zero and we need to return empty
-        if self.return_range.start == usize::MAX {
-            Bytes::new()
-        } else {
-            Bytes::copy_from_slice(self.memory.get_slice(
-                self.return_range.start,
-                self.return_range.end - self.return_range.start,
-            ))
-        }
-    }
 }
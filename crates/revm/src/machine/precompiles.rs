@@ -0,0 +1,84 @@
+use super::{CostType, Gas};
+use crate::instructions::Return;
+use bytes::Bytes;
+use primitive_types::H160;
+use sha3::{Digest, Keccak256};
+
+/// Addresses `0x01..=0x09` are reserved for native precompiled contracts.
+/// Only `ecrecover` (`0x01`) is implemented and dispatched; every other
+/// address, including `0x02..=0x09`, returns `None`.
+pub fn call_precompile<T: CostType>(
+    address: H160,
+    input: &Bytes,
+    gas: &mut Gas<T>,
+) -> Option<(Return, Bytes)> {
+    if address > H160::from_low_u64_be(0x09) || address == H160::zero() {
+        return None;
+    }
+
+    match address.to_low_u64_be() {
+        1 => Some(ecrecover(input, gas)),
+        _ => None,
+    }
+}
+
+const ECRECOVER_COST: u64 = 3000;
+
+/// `ECRECOVER` (address `0x01`). Input is `hash(32) || v(32) || r(32) || s(32)`;
+/// returns the recovered address left-padded to 32 bytes, or an empty output
+/// (not an error) if recovery fails for any reason.
+fn ecrecover<T: CostType>(input: &Bytes, gas: &mut Gas<T>) -> (Return, Bytes) {
+    if !gas.record_cost(T::from_u64(ECRECOVER_COST)) {
+        return (Return::OutOfGas, Bytes::new());
+    }
+
+    let mut buf = [0u8; 128];
+    let len = input.len().min(128);
+    buf[..len].copy_from_slice(&input[..len]);
+
+    let hash = &buf[0..32];
+    let v = &buf[32..64];
+    let r = &buf[64..96];
+    let s = &buf[96..128];
+
+    // v must be 27 or 28, encoded as a 32-byte big-endian integer with no
+    // other bits set.
+    if v[..31].iter().any(|b| *b != 0) || (v[31] != 27 && v[31] != 28) {
+        return (Return::Return, Bytes::new());
+    }
+    let recovery_id = match secp256k1::recovery::RecoveryId::from_i32((v[31] - 27) as i32) {
+        Ok(id) => id,
+        Err(_) => return (Return::Return, Bytes::new()),
+    };
+
+    // r and s must each be non-zero and below the curve order; the
+    // secp256k1 crate enforces this for us: out-of-range values are rejected
+    // when parsing the signature, zero values when recovering the key.
+    let mut sig_bytes = [0u8; 64];
+    sig_bytes[..32].copy_from_slice(r);
+    sig_bytes[32..].copy_from_slice(s);
+    let signature =
+        match secp256k1::recovery::RecoverableSignature::from_compact(&sig_bytes, recovery_id) {
+            Ok(signature) => signature,
+            Err(_) => return (Return::Return, Bytes::new()),
+        };
+
+    let message = match secp256k1::Message::from_slice(hash) {
+        Ok(message) => message,
+        Err(_) => return (Return::Return, Bytes::new()),
+    };
+
+    let secp = secp256k1::Secp256k1::new();
+    let public_key = match secp.recover(&message, &signature) {
+        Ok(public_key) => public_key,
+        Err(_) => return (Return::Return, Bytes::new()),
+    };
+
+    // Drop the leading 0x04 (uncompressed point tag) before hashing, then
+    // keep the last 20 bytes of the keccak256 hash as the address.
+    let hashed = Keccak256::digest(&public_key.serialize_uncompressed()[1..]);
+
+    let mut out = [0u8; 32];
+    out[12..].copy_from_slice(&hashed[12..]);
+    (Return::Return, Bytes::copy_from_slice(&out))
+}
@@ -0,0 +1,22 @@
+/// One EIP-3155-style structured record emitted per executed opcode when
+/// the `tracing` feature is enabled.
+#[derive(Clone, Debug)]
+pub struct OpcodeTrace {
+    pub program_counter: usize,
+    pub opcode: u8,
+    pub gas_remaining: u64,
+    pub gas_cost: u64,
+    pub stack_depth: usize,
+    pub memory_size: usize,
+}
+
+/// Receives one `OpcodeTrace` per opcode executed by `Machine::step`.
+///
+/// This is a separate, compile-time-gated path from the `Host::step`/
+/// `step_end` inspector hooks: it exists purely to produce a canonical,
+/// machine-readable opcode trace (e.g. for differential testing against
+/// other EVM implementations) without requiring callers to implement a full
+/// inspector. It composes with the inspector path rather than replacing it.
+pub trait Tracer {
+    fn trace_opcode(&mut self, trace: OpcodeTrace);
+}